@@ -18,18 +18,176 @@
 //! Nom parsers for RPC & NFSv3
 
 use std::cmp;
-use nom::{IResult, be_u32, be_u64, rest};
+use nom::{IResult, be_u32, be_u64, rest, Err, ErrorKind, Context};
 use crate::nfs::nfs_records::*;
 
+/// Maximum number of READDIR/READDIRPLUS entries accepted from a single
+/// reply. Bounds the `many0` entry loop so a truncated or hostile stream
+/// can't spin it indefinitely.
+pub const NFS3_MAX_READDIR_ENTRIES: usize = 4096;
+
+/// nfsstat3 NFS3_OK, as defined in RFC 1813 Section 2.6. Replies whose
+/// status differs from this carry the `resfail` arm of their XDR union
+/// instead of `resok`, which is usually a much smaller payload.
+pub const NFS3_OK: u32 = 0;
+
+/// NFSv3 parser anomalies. Parsers in this module report them as the
+/// `ErrorKind::Custom` code of a nom parse error; nothing in this module
+/// registers or raises them as application-layer events.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Nfs3Event {
+    InvalidReaddirCount = 0,
+}
+
+/// Reject a READDIR/READDIRPLUS count argument, raising `Nfs3Event::InvalidReaddirCount`
+/// as a custom nom error so the caller can turn it into an app-layer event
+/// instead of a silent parse failure.
+fn nfs3_invalid_readdir_count<'a>(i: &'a [u8]) -> Err<&'a [u8]> {
+    Err::Error(error_position!(i, ErrorKind::Custom(Nfs3Event::InvalidReaddirCount as u32)))
+}
+
 #[derive(Debug,PartialEq)]
 pub struct Nfs3Handle<'a> {
     pub len: u32,
     pub value: &'a[u8],
 }
 
+/// NFSv3 fattr3, as described in RFC 1813 Section 2.3.3. Always 84 bytes
+/// on the wire.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3Fattr {
+    pub obj_type: u32,
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub used: u64,
+    pub specdata1: u32,
+    pub specdata2: u32,
+    pub fsid: u64,
+    pub fileid: u64,
+    pub atime_secs: u32,
+    pub atime_nsecs: u32,
+    pub mtime_secs: u32,
+    pub mtime_nsecs: u32,
+    pub ctime_secs: u32,
+    pub ctime_nsecs: u32,
+}
+
+named!(pub parse_nfs3_fattr<Nfs3Fattr>,
+    do_parse!(
+           obj_type: be_u32
+        >> mode: be_u32
+        >> nlink: be_u32
+        >> uid: be_u32
+        >> gid: be_u32
+        >> size: be_u64
+        >> used: be_u64
+        >> specdata1: be_u32
+        >> specdata2: be_u32
+        >> fsid: be_u64
+        >> fileid: be_u64
+        >> atime_secs: be_u32
+        >> atime_nsecs: be_u32
+        >> mtime_secs: be_u32
+        >> mtime_nsecs: be_u32
+        >> ctime_secs: be_u32
+        >> ctime_nsecs: be_u32
+        >> (
+            Nfs3Fattr {
+                obj_type,
+                mode,
+                nlink,
+                uid,
+                gid,
+                size,
+                used,
+                specdata1,
+                specdata2,
+                fsid,
+                fileid,
+                atime_secs,
+                atime_nsecs,
+                mtime_secs,
+                mtime_nsecs,
+                ctime_secs,
+                ctime_nsecs,
+            }
+        ))
+);
+
+/// NFSv3 wcc_attr: the subset of fattr3 carried in a pre-op attribute,
+/// as described in RFC 1813 Section 2.6. Always 24 bytes on the wire.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3PreOpAttr {
+    pub size: u64,
+    pub mtime_secs: u32,
+    pub mtime_nsecs: u32,
+    pub ctime_secs: u32,
+    pub ctime_nsecs: u32,
+}
+
+named!(pub parse_nfs3_wcc_attr<Nfs3PreOpAttr>,
+    do_parse!(
+           size: be_u64
+        >> mtime_secs: be_u32
+        >> mtime_nsecs: be_u32
+        >> ctime_secs: be_u32
+        >> ctime_nsecs: be_u32
+        >> (
+            Nfs3PreOpAttr {
+                size,
+                mtime_secs,
+                mtime_nsecs,
+                ctime_secs,
+                ctime_nsecs,
+            }
+        ))
+);
+
+named!(pub parse_nfs3_pre_op_attr<Option<Nfs3PreOpAttr>>,
+    do_parse!(
+           value_follows: verify!(be_u32, |v| v <= 1)
+        >> attr: cond!(value_follows == 1, parse_nfs3_wcc_attr)
+        >> ( attr ))
+);
+
+named!(pub parse_nfs3_post_op_attr<Option<Nfs3Fattr>>,
+    do_parse!(
+           value_follows: verify!(be_u32, |v| v <= 1)
+        >> attr: cond!(value_follows == 1, parse_nfs3_fattr)
+        >> ( attr ))
+);
+
+/// NFSv3 wcc_data: the weak-cache-consistency data carried alongside most
+/// mutating replies, giving the before/after attributes of the object
+/// that was acted on (RFC 1813 Section 2.6).
+#[derive(Debug,PartialEq)]
+pub struct Nfs3WccData {
+    pub before: Option<Nfs3PreOpAttr>,
+    pub after: Option<Nfs3Fattr>,
+}
+
+named!(pub parse_nfs3_wcc_data<Nfs3WccData>,
+    do_parse!(
+           before: parse_nfs3_pre_op_attr
+        >> after: parse_nfs3_post_op_attr
+        >> (
+            Nfs3WccData {
+                before,
+                after,
+            }
+        ))
+);
+
+/// Maximum size in bytes of an NFSv3 file handle, as defined by
+/// NFS3_FHSIZE in RFC 1813 Section 2.3.3.
+pub const NFS3_FHSIZE: u32 = 64;
+
 named!(pub parse_nfs3_handle<Nfs3Handle>,
     do_parse!(
-        obj_len: be_u32
+        obj_len: verify!(be_u32, |v| v <= NFS3_FHSIZE)
         >> obj: take!(obj_len)
         >> (
             Nfs3Handle {
@@ -43,21 +201,304 @@ named!(pub parse_nfs3_handle<Nfs3Handle>,
 pub struct Nfs3ReplyCreate<'a> {
     pub status: u32,
     pub handle: Option<Nfs3Handle<'a>>,
+    pub attr: Option<Nfs3Fattr>,
+    pub dir_wcc: Nfs3WccData,
+}
+
+/// CREATE3resok carries `obj`/`obj_attributes` in addition to `dir_wcc`;
+/// CREATE3resfail (RFC 1813 Section 3.3.1) is just `dir_wcc`, so those
+/// fields are only present when `status` is `NFS3_OK`.
+pub fn parse_nfs3_response_create(i: &[u8]) -> IResult<&[u8], Nfs3ReplyCreate> {
+    let (i, status) = be_u32(i)?;
+    let (i, handle) = if status == NFS3_OK {
+        let (i, handle_has_value) = verify!(i, be_u32, |v| v <= 1)?;
+        cond!(i, handle_has_value == 1, parse_nfs3_handle)?
+    } else {
+        (i, None)
+    };
+    let (i, attr) = if status == NFS3_OK {
+        parse_nfs3_post_op_attr(i)?
+    } else {
+        (i, None)
+    };
+    let (i, dir_wcc) = parse_nfs3_wcc_data(i)?;
+    Ok((i, Nfs3ReplyCreate {
+        status,
+        handle,
+        attr,
+        dir_wcc,
+    }))
+}
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyRemove {
+    pub status: u32,
+    pub dir_wcc: Nfs3WccData,
+}
+
+named!(pub parse_nfs3_response_remove<Nfs3ReplyRemove>,
+    do_parse!(
+        status: be_u32
+        >> dir_wcc: parse_nfs3_wcc_data
+        >> (
+            Nfs3ReplyRemove {
+               status,
+               dir_wcc,
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyRmdir {
+    pub status: u32,
+    pub dir_wcc: Nfs3WccData,
+}
+
+named!(pub parse_nfs3_response_rmdir<Nfs3ReplyRmdir>,
+    do_parse!(
+        status: be_u32
+        >> dir_wcc: parse_nfs3_wcc_data
+        >> (
+            Nfs3ReplyRmdir {
+               status,
+               dir_wcc,
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyMkdir<'a> {
+    pub status: u32,
+    pub handle: Option<Nfs3Handle<'a>>,
+    pub attr: Option<Nfs3Fattr>,
+    pub dir_wcc: Nfs3WccData,
+}
+
+/// MKDIR3resok carries `obj`/`obj_attributes` in addition to `dir_wcc`;
+/// MKDIR3resfail (RFC 1813 Section 3.3.9) is just `dir_wcc`, so those
+/// fields are only present when `status` is `NFS3_OK`.
+pub fn parse_nfs3_response_mkdir(i: &[u8]) -> IResult<&[u8], Nfs3ReplyMkdir> {
+    let (i, status) = be_u32(i)?;
+    let (i, handle) = if status == NFS3_OK {
+        let (i, handle_has_value) = verify!(i, be_u32, |v| v <= 1)?;
+        cond!(i, handle_has_value == 1, parse_nfs3_handle)?
+    } else {
+        (i, None)
+    };
+    let (i, attr) = if status == NFS3_OK {
+        parse_nfs3_post_op_attr(i)?
+    } else {
+        (i, None)
+    };
+    let (i, dir_wcc) = parse_nfs3_wcc_data(i)?;
+    Ok((i, Nfs3ReplyMkdir {
+        status,
+        handle,
+        attr,
+        dir_wcc,
+    }))
+}
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyRename {
+    pub status: u32,
+    pub fromdir_wcc: Nfs3WccData,
+    pub todir_wcc: Nfs3WccData,
 }
 
-named!(pub parse_nfs3_response_create<Nfs3ReplyCreate>,
+named!(pub parse_nfs3_response_rename<Nfs3ReplyRename>,
     do_parse!(
         status: be_u32
-        >> handle_has_value: verify!(be_u32, |v| v <= 1)
-        >> handle: cond!(handle_has_value == 1, parse_nfs3_handle)
+        >> fromdir_wcc: parse_nfs3_wcc_data
+        >> todir_wcc: parse_nfs3_wcc_data
+        >> (
+            Nfs3ReplyRename {
+               status,
+               fromdir_wcc,
+               todir_wcc,
+            }
+        ))
+);
+
+/// The `resok`-only fields of FSINFO3resok (everything but `obj_attributes`),
+/// as described in RFC 1813 Section 3.3.19. Absent on FSINFO3resfail.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3FsinfoResok {
+    pub rtmax: u32,
+    pub rtpref: u32,
+    pub rtmult: u32,
+    pub wtmax: u32,
+    pub wtpref: u32,
+    pub wtmult: u32,
+    pub dtpref: u32,
+    pub maxfilesize: u64,
+    pub time_delta_secs: u32,
+    pub time_delta_nsecs: u32,
+    pub properties: u32,
+}
+
+named!(parse_nfs3_fsinfo_resok<Nfs3FsinfoResok>,
+    do_parse!(
+        rtmax: be_u32
+        >> rtpref: be_u32
+        >> rtmult: be_u32
+        >> wtmax: be_u32
+        >> wtpref: be_u32
+        >> wtmult: be_u32
+        >> dtpref: be_u32
+        >> maxfilesize: be_u64
+        >> time_delta_secs: be_u32
+        >> time_delta_nsecs: be_u32
+        >> properties: be_u32
+        >> (
+            Nfs3FsinfoResok {
+               rtmax,
+               rtpref,
+               rtmult,
+               wtmax,
+               wtpref,
+               wtmult,
+               dtpref,
+               maxfilesize,
+               time_delta_secs,
+               time_delta_nsecs,
+               properties,
+            }
+        ))
+);
+
+/// NFSv3 FSINFO3res: the non-volatile filesystem/server limits negotiated
+/// for a mounted export, as described in RFC 1813 Section 3.3.19.
+/// `info` is only present when `status` is `NFS3_OK`; FSINFO3resfail is
+/// just `obj_attributes`.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyFsinfo {
+    pub status: u32,
+    pub attr: Option<Nfs3Fattr>,
+    pub info: Option<Nfs3FsinfoResok>,
+}
+
+pub fn parse_nfs3_response_fsinfo(i: &[u8]) -> IResult<&[u8], Nfs3ReplyFsinfo> {
+    let (i, status) = be_u32(i)?;
+    let (i, attr) = parse_nfs3_post_op_attr(i)?;
+    let (i, info) = cond!(i, status == NFS3_OK, parse_nfs3_fsinfo_resok)?;
+    Ok((i, Nfs3ReplyFsinfo {
+        status,
+        attr,
+        info,
+    }))
+}
+
+/// The `resok`-only fields of FSSTAT3resok (everything but `obj_attributes`),
+/// as described in RFC 1813 Section 3.3.18. Absent on FSSTAT3resfail.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3FsstatResok {
+    pub tbytes: u64,
+    pub fbytes: u64,
+    pub abytes: u64,
+    pub tfiles: u64,
+    pub ffiles: u64,
+    pub afiles: u64,
+    pub invarsec: u32,
+}
+
+named!(parse_nfs3_fsstat_resok<Nfs3FsstatResok>,
+    do_parse!(
+        tbytes: be_u64
+        >> fbytes: be_u64
+        >> abytes: be_u64
+        >> tfiles: be_u64
+        >> ffiles: be_u64
+        >> afiles: be_u64
+        >> invarsec: be_u32
+        >> (
+            Nfs3FsstatResok {
+               tbytes,
+               fbytes,
+               abytes,
+               tfiles,
+               ffiles,
+               afiles,
+               invarsec,
+            }
+        ))
+);
+
+/// NFSv3 FSSTAT3res: dynamic filesystem usage/capacity counters, as
+/// described in RFC 1813 Section 3.3.18. `info` is only present when
+/// `status` is `NFS3_OK`; FSSTAT3resfail is just `obj_attributes`.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyFsstat {
+    pub status: u32,
+    pub attr: Option<Nfs3Fattr>,
+    pub info: Option<Nfs3FsstatResok>,
+}
+
+pub fn parse_nfs3_response_fsstat(i: &[u8]) -> IResult<&[u8], Nfs3ReplyFsstat> {
+    let (i, status) = be_u32(i)?;
+    let (i, attr) = parse_nfs3_post_op_attr(i)?;
+    let (i, info) = cond!(i, status == NFS3_OK, parse_nfs3_fsstat_resok)?;
+    Ok((i, Nfs3ReplyFsstat {
+        status,
+        attr,
+        info,
+    }))
+}
+
+/// The `resok`-only fields of PATHCONF3resok (everything but `obj_attributes`),
+/// as described in RFC 1813 Section 3.3.20. Absent on PATHCONF3resfail.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3PathconfResok {
+    pub linkmax: u32,
+    pub name_max: u32,
+    pub no_trunc: u32,
+    pub chown_restricted: u32,
+    pub case_insensitive: u32,
+    pub case_preserving: u32,
+}
+
+named!(parse_nfs3_pathconf_resok<Nfs3PathconfResok>,
+    do_parse!(
+        linkmax: be_u32
+        >> name_max: be_u32
+        >> no_trunc: be_u32
+        >> chown_restricted: be_u32
+        >> case_insensitive: be_u32
+        >> case_preserving: be_u32
         >> (
-            Nfs3ReplyCreate {
-               status:status,
-               handle:handle,
+            Nfs3PathconfResok {
+               linkmax,
+               name_max,
+               no_trunc,
+               chown_restricted,
+               case_insensitive,
+               case_preserving,
             }
         ))
 );
 
+/// NFSv3 PATHCONF3res: POSIX pathconf-style limits and behaviour flags
+/// for a given object, as described in RFC 1813 Section 3.3.20. `info` is
+/// only present when `status` is `NFS3_OK`; PATHCONF3resfail is just
+/// `obj_attributes`.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ReplyPathconf {
+    pub status: u32,
+    pub attr: Option<Nfs3Fattr>,
+    pub info: Option<Nfs3PathconfResok>,
+}
+
+pub fn parse_nfs3_response_pathconf(i: &[u8]) -> IResult<&[u8], Nfs3ReplyPathconf> {
+    let (i, status) = be_u32(i)?;
+    let (i, attr) = parse_nfs3_post_op_attr(i)?;
+    let (i, info) = cond!(i, status == NFS3_OK, parse_nfs3_pathconf_resok)?;
+    Ok((i, Nfs3ReplyPathconf {
+        status,
+        attr,
+        info,
+    }))
+}
+
 #[derive(Debug,PartialEq)]
 pub struct Nfs3ReplyLookup<'a> {
     pub status: u32,
@@ -209,6 +650,51 @@ named!(pub parse_nfs3_request_getattr<Nfs3RequestGetAttr>,
         ))
 );
 
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestFsstat<'a> {
+    pub handle: Nfs3Handle<'a>,
+}
+
+named!(pub parse_nfs3_request_fsstat<Nfs3RequestFsstat>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >> (
+            Nfs3RequestFsstat {
+                handle,
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestFsinfo<'a> {
+    pub handle: Nfs3Handle<'a>,
+}
+
+named!(pub parse_nfs3_request_fsinfo<Nfs3RequestFsinfo>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >> (
+            Nfs3RequestFsinfo {
+                handle,
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestPathconf<'a> {
+    pub handle: Nfs3Handle<'a>,
+}
+
+named!(pub parse_nfs3_request_pathconf<Nfs3RequestPathconf>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >> (
+            Nfs3RequestPathconf {
+                handle,
+            }
+        ))
+);
+
 #[derive(Debug,PartialEq)]
 pub struct Nfs3RequestAccess<'a> {
     pub handle: Nfs3Handle<'a>,
@@ -284,10 +770,158 @@ named!(pub parse_nfs3_request_lookup<Nfs3RequestLookup>,
         ))
 );
 
+/// NFSv3 sattr3, the set of optionally-present attributes that SETATTR,
+/// MKDIR, SYMLINK and MKNOD carry for the object being created/modified
+/// (RFC 1813 Section 2.3.4).
+#[derive(Debug,PartialEq)]
+pub struct Nfs3Sattr3 {
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub size: Option<u64>,
+    pub atime: Option<(u32,u32)>,
+    pub mtime: Option<(u32,u32)>,
+}
+
+named!(pub parse_nfs3_sattr3<Nfs3Sattr3>,
+    do_parse!(
+           mode_set_it: verify!(be_u32, |v| v <= 1)
+        >> mode: cond!(mode_set_it == 1, be_u32)
+        >> uid_set_it: verify!(be_u32, |v| v <= 1)
+        >> uid: cond!(uid_set_it == 1, be_u32)
+        >> gid_set_it: verify!(be_u32, |v| v <= 1)
+        >> gid: cond!(gid_set_it == 1, be_u32)
+        >> size_set_it: verify!(be_u32, |v| v <= 1)
+        >> size: cond!(size_set_it == 1, be_u64)
+        >> atime_set_how: verify!(be_u32, |v| v <= 2)
+        >> atime_secs: cond!(atime_set_how == 2, be_u32)
+        >> atime_nsecs: cond!(atime_set_how == 2, be_u32)
+        >> mtime_set_how: verify!(be_u32, |v| v <= 2)
+        >> mtime_secs: cond!(mtime_set_how == 2, be_u32)
+        >> mtime_nsecs: cond!(mtime_set_how == 2, be_u32)
+        >> (
+            Nfs3Sattr3 {
+                mode,
+                uid,
+                gid,
+                size,
+                atime: atime_secs.map(|s| (s, atime_nsecs.unwrap_or(0))),
+                mtime: mtime_secs.map(|s| (s, mtime_nsecs.unwrap_or(0))),
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestSetattr<'a> {
+    pub handle: Nfs3Handle<'a>,
+    pub attrs: Nfs3Sattr3,
+    pub check_guard: u32,
+    pub guard_ctime: Option<(u32,u32)>,
+}
+
+named!(pub parse_nfs3_request_setattr<Nfs3RequestSetattr>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >>  attrs: parse_nfs3_sattr3
+        >>  check_guard: verify!(be_u32, |v| v <= 1)
+        >>  guard_ctime_secs: cond!(check_guard == 1, be_u32)
+        >>  guard_ctime_nsecs: cond!(check_guard == 1, be_u32)
+        >> (
+            Nfs3RequestSetattr {
+                handle,
+                attrs,
+                check_guard,
+                guard_ctime: guard_ctime_secs.map(|s| (s, guard_ctime_nsecs.unwrap_or(0))),
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestSymlink<'a> {
+    pub handle: Nfs3Handle<'a>,
+    pub name_vec: Vec<u8>,
+    pub attrs: Nfs3Sattr3,
+    pub target_vec: Vec<u8>,
+}
+
+named!(pub parse_nfs3_request_symlink<Nfs3RequestSymlink>,
+    do_parse!(
+            dir_handle: parse_nfs3_handle
+        >>  name_len: be_u32
+        >>  name: take!(name_len)
+        >>  _name_fill_bytes: cond!(name_len % 4 != 0, take!(4 - name_len % 4))
+        >>  attrs: parse_nfs3_sattr3
+        >>  target_len: be_u32
+        >>  target: take!(target_len)
+        >>  _target_fill_bytes: rest
+        >> (
+            Nfs3RequestSymlink {
+                handle:dir_handle,
+                name_vec:name.to_vec(),
+                attrs,
+                target_vec:target.to_vec(),
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestMknod<'a> {
+    pub handle: Nfs3Handle<'a>,
+    pub name_vec: Vec<u8>,
+    pub ftype: u32,
+    pub specdata: Option<(u32,u32)>,
+}
+
+named!(pub parse_nfs3_request_mknod<Nfs3RequestMknod>,
+    do_parse!(
+            dir_handle: parse_nfs3_handle
+        >>  name_len: be_u32
+        >>  name: take!(name_len)
+        >>  _name_fill_bytes: cond!(name_len % 4 != 0, take!(4 - name_len % 4))
+        >>  ftype: be_u32
+        // NF3BLK (3) and NF3CHR (4) carry a devicedata3 (specdata3 + sattr3);
+        // other types carry only an sattr3, which we don't need here.
+        >>  specdata1: cond!(ftype == 3 || ftype == 4, be_u32)
+        >>  specdata2: cond!(ftype == 3 || ftype == 4, be_u32)
+        >>  _attrs: rest
+        >> (
+            Nfs3RequestMknod {
+                handle:dir_handle,
+                name_vec:name.to_vec(),
+                ftype,
+                specdata: specdata1.map(|s1| (s1, specdata2.unwrap_or(0))),
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestLink<'a> {
+    pub handle: Nfs3Handle<'a>,
+    pub dir_handle: Nfs3Handle<'a>,
+    pub name_vec: Vec<u8>,
+}
+
+named!(pub parse_nfs3_request_link<Nfs3RequestLink>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >>  dir_handle: parse_nfs3_handle
+        >>  name_len: be_u32
+        >>  name: take!(name_len)
+        >>  _fill_bytes: rest
+        >> (
+            Nfs3RequestLink {
+                handle,
+                dir_handle,
+                name_vec:name.to_vec(),
+            }
+        ))
+);
+
 
 #[derive(Debug,PartialEq)]
 pub struct Nfs3ResponseReaddirplusEntryC<'a> {
     pub name_vec: Vec<u8>,
+    pub attr: Option<Nfs3Fattr>,
     pub handle: Option<Nfs3Handle<'a>>,
 }
 
@@ -299,12 +933,13 @@ named!(pub parse_nfs3_response_readdirplus_entry<Nfs3ResponseReaddirplusEntryC>,
         >> _fill_bytes: cond!(name_len % 4 != 0, take!(4 - name_len % 4))
         >> _cookie: take!(8)
         >> attr_value_follows: verify!(be_u32, |v| v <= 1)
-        >> _attr: cond!(attr_value_follows==1, take!(84))
+        >> attr: cond!(attr_value_follows==1, parse_nfs3_fattr)
         >> handle_value_follows: verify!(be_u32, |v| v <= 1)
         >> handle: cond!(handle_value_follows==1, parse_nfs3_handle)
         >> (
                 Nfs3ResponseReaddirplusEntryC {
                     name_vec:name_content.to_vec(),
+                    attr,
                     handle,
                 }
            )
@@ -327,28 +962,51 @@ named!(pub parse_nfs3_response_readdirplus_entry_cond<Nfs3ResponseReaddirplusEnt
            ))
 );
 
+/// READDIRPLUS3resok carries `cookieverf`/`reply` in addition to
+/// `dir_attributes`; READDIRPLUS3resfail (RFC 1813 Section 3.3.17) is just
+/// `dir_attributes`, so `data` is only present when `status` is `NFS3_OK`.
 #[derive(Debug,PartialEq)]
 pub struct Nfs3ResponseReaddirplus<'a> {
     pub status: u32,
-    pub data: &'a[u8],
+    pub dir_attr: Option<Nfs3Fattr>,
+    pub data: Option<&'a[u8]>,
 }
 
-named!(pub parse_nfs3_response_readdirplus<Nfs3ResponseReaddirplus>,
-    do_parse!(
-        status: be_u32
-        >> dir_attr_follows: verify!(be_u32, |v| v <= 1)
-        >> _dir_attr: cond!(dir_attr_follows == 1, take!(84))
-        >> _verifier: take!(8)
-        >> data: rest
-
-        >> ( Nfs3ResponseReaddirplus {
-                status,
-                data
-        } ))
-);
+pub fn parse_nfs3_response_readdirplus(i: &[u8]) -> IResult<&[u8], Nfs3ResponseReaddirplus> {
+    let (i, status) = be_u32(i)?;
+    let (i, dir_attr_follows) = verify!(i, be_u32, |v| v <= 1)?;
+    let (i, dir_attr) = cond!(i, dir_attr_follows == 1, parse_nfs3_fattr)?;
+    let (i, data) = if status == NFS3_OK {
+        let (i, _verifier) = take!(i, 8)?;
+        let (i, data) = rest(i)?;
+        (i, Some(data))
+    } else {
+        (i, None)
+    };
+    Ok((i, Nfs3ResponseReaddirplus {
+        status,
+        dir_attr,
+        data,
+    }))
+}
 
 pub(crate) fn many0_nfs3_response_readdirplus_entries<'a>(input: &'a [u8]) -> IResult<&'a[u8], Vec<Nfs3ResponseReaddirplusEntry<'a>>> {
-    many0!(input, complete!(parse_nfs3_response_readdirplus_entry_cond))
+    let mut entries = Vec::new();
+    let mut i = input;
+    while entries.len() < NFS3_MAX_READDIR_ENTRIES {
+        match complete!(i, parse_nfs3_response_readdirplus_entry_cond) {
+            Ok((rem, entry)) => {
+                let has_more = entry.entry.is_some();
+                i = rem;
+                entries.push(entry);
+                if !has_more {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    Ok((i, entries))
 }
 
 #[derive(Debug,PartialEq)]
@@ -361,13 +1019,24 @@ pub struct Nfs3RequestReaddirplus<'a> {
     pub maxcount: u32,
 }
 
+// a zero or smaller-than-dircount maxcount is a known trigger for
+// buffer-size underflows on some NFSv3 servers; reject it as an
+// Nfs3Event::InvalidReaddirCount anomaly rather than parsing it.
+fn parse_nfs3_request_readdirplus_maxcount(i: &[u8], dircount: u32) -> IResult<&[u8], u32> {
+    let (i, maxcount) = be_u32(i)?;
+    if maxcount == 0 || maxcount < dircount {
+        return Err(nfs3_invalid_readdir_count(i));
+    }
+    Ok((i, maxcount))
+}
+
 named!(pub parse_nfs3_request_readdirplus<Nfs3RequestReaddirplus>,
     do_parse!(
             handle: parse_nfs3_handle
         >>  cookie: be_u32
         >>  verifier: take!(8)
         >>  dircount: be_u32
-        >>  maxcount: be_u32
+        >>  maxcount: call!(parse_nfs3_request_readdirplus_maxcount, dircount)
         >> (
             Nfs3RequestReaddirplus {
                 handle:handle,
@@ -379,6 +1048,128 @@ named!(pub parse_nfs3_request_readdirplus<Nfs3RequestReaddirplus>,
         ))
 );
 
+#[derive(Debug,PartialEq)]
+pub struct Nfs3RequestReaddir<'a> {
+    pub handle: Nfs3Handle<'a>,
+
+    pub cookie: u64,
+    pub verifier: &'a[u8],
+    pub count: u32,
+}
+
+// a zero count asks the server for a reply that can't carry any entries;
+// reject it as an Nfs3Event::InvalidReaddirCount anomaly like READDIRPLUS's
+// maxcount, rather than parsing it.
+fn parse_nfs3_request_readdir_count(i: &[u8]) -> IResult<&[u8], u32> {
+    let (i, count) = be_u32(i)?;
+    if count == 0 {
+        return Err(nfs3_invalid_readdir_count(i));
+    }
+    Ok((i, count))
+}
+
+named!(pub parse_nfs3_request_readdir<Nfs3RequestReaddir>,
+    do_parse!(
+            handle: parse_nfs3_handle
+        >>  cookie: be_u64
+        >>  verifier: take!(8)
+        >>  count: call!(parse_nfs3_request_readdir_count)
+        >> (
+            Nfs3RequestReaddir {
+                handle,
+                cookie,
+                verifier,
+                count,
+            }
+        ))
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ResponseReaddirEntryC {
+    pub file_id: u64,
+    pub name_vec: Vec<u8>,
+    pub cookie: u64,
+}
+
+named!(pub parse_nfs3_response_readdir_entry<Nfs3ResponseReaddirEntryC>,
+    do_parse!(
+           file_id: be_u64
+        >> name_len: be_u32
+        >> name_content: take!(name_len)
+        >> _fill_bytes: cond!(name_len % 4 != 0, take!(4 - name_len % 4))
+        >> cookie: be_u64
+        >> (
+                Nfs3ResponseReaddirEntryC {
+                    file_id,
+                    name_vec:name_content.to_vec(),
+                    cookie,
+                }
+           )
+        )
+);
+
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ResponseReaddirEntry {
+    pub entry: Option<Nfs3ResponseReaddirEntryC>,
+}
+
+named!(pub parse_nfs3_response_readdir_entry_cond<Nfs3ResponseReaddirEntry>,
+    do_parse!(
+           value_follows: verify!(be_u32, |v| v <= 1)
+        >> entry: cond!(value_follows==1, parse_nfs3_response_readdir_entry)
+        >> (
+            Nfs3ResponseReaddirEntry {
+                entry
+            }
+           ))
+);
+
+pub(crate) fn many0_nfs3_response_readdir_entries(input: &[u8]) -> IResult<&[u8], Vec<Nfs3ResponseReaddirEntry>> {
+    let mut entries = Vec::new();
+    let mut i = input;
+    while entries.len() < NFS3_MAX_READDIR_ENTRIES {
+        match complete!(i, parse_nfs3_response_readdir_entry_cond) {
+            Ok((rem, entry)) => {
+                let has_more = entry.entry.is_some();
+                i = rem;
+                entries.push(entry);
+                if !has_more {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    Ok((i, entries))
+}
+
+/// READDIR3resok carries `cookieverf`/`reply` in addition to
+/// `dir_attributes`; READDIR3resfail (RFC 1813 Section 3.3.16) is just
+/// `dir_attributes`, so `data` is only present when `status` is `NFS3_OK`.
+#[derive(Debug,PartialEq)]
+pub struct Nfs3ResponseReaddir<'a> {
+    pub status: u32,
+    pub dir_attr: Option<Nfs3Fattr>,
+    pub data: Option<&'a[u8]>,
+}
+
+pub fn parse_nfs3_response_readdir(i: &[u8]) -> IResult<&[u8], Nfs3ResponseReaddir> {
+    let (i, status) = be_u32(i)?;
+    let (i, dir_attr) = parse_nfs3_post_op_attr(i)?;
+    let (i, data) = if status == NFS3_OK {
+        let (i, _verifier) = take!(i, 8)?;
+        let (i, data) = rest(i)?;
+        (i, Some(data))
+    } else {
+        (i, None)
+    };
+    Ok((i, Nfs3ResponseReaddir {
+        status,
+        dir_attr,
+        data,
+    }))
+}
+
 #[derive(Debug,PartialEq)]
 pub struct Nfs3RequestWrite<'a> {
     pub handle: Nfs3Handle<'a>,
@@ -432,7 +1223,7 @@ pub fn parse_nfs3_request_write(i: &[u8], complete: bool) -> IResult<&[u8], Nfs3
 pub fn parse_nfs3_reply_read(i: &[u8], complete: bool) -> IResult<&[u8], NfsReplyRead> {
     let (i, status) = be_u32(i)?;
     let (i, attr_follows) = verify!(i, be_u32, |v| v <= 1)?;
-    let (i, attr_blob) = take!(i, 84_usize)?; // fixed size?
+    let (i, attr) = cond!(i, attr_follows == 1, parse_nfs3_fattr)?;
     let (i, count) = be_u32(i)?;
     let (i, eof) = verify!(i, be_u32, |v| v <= 1)?;
     let (i, data_len) = verify!(i, be_u32, |v| v <= count)?;
@@ -448,7 +1239,7 @@ pub fn parse_nfs3_reply_read(i: &[u8], complete: bool) -> IResult<&[u8], NfsRepl
     let reply = NfsReplyRead {
         status,
         attr_follows,
-        attr_blob,
+        attr,
         count,
         eof: eof != 0,
         data_len,
@@ -456,3 +1247,163 @@ pub fn parse_nfs3_reply_read(i: &[u8], complete: bool) -> IResult<&[u8], NfsRepl
     };
     Ok((i, reply))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nfs3_handle_max_size() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(NFS3_FHSIZE as u32).to_be_bytes());
+        buf.extend_from_slice(&[0u8; NFS3_FHSIZE as usize]);
+        let result = parse_nfs3_handle(&buf);
+        assert!(result.is_ok());
+        let (rem, handle) = result.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(handle.len, NFS3_FHSIZE);
+    }
+
+    #[test]
+    fn test_parse_nfs3_handle_too_large() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(NFS3_FHSIZE as u32 + 1).to_be_bytes());
+        buf.extend_from_slice(&[0u8; NFS3_FHSIZE as usize + 1]);
+        let result = parse_nfs3_handle(&buf);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_nfs3_handle_zero_size() {
+        let buf = (0u32).to_be_bytes();
+        let result = parse_nfs3_handle(&buf);
+        assert!(result.is_ok());
+        let (rem, handle) = result.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(handle.len, 0);
+        assert_eq!(handle.value.len(), 0);
+    }
+
+    #[test]
+    fn test_parse_nfs3_response_mkdir_ok() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(NFS3_OK).to_be_bytes());        // status
+        buf.extend_from_slice(&(1u32).to_be_bytes());           // obj: value follows
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // obj: zero-length handle
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // obj_attributes: not present
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // dir_wcc.before: not present
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // dir_wcc.after: not present
+
+        let (rem, reply) = parse_nfs3_response_mkdir(&buf).unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(reply.status, NFS3_OK);
+        assert!(reply.handle.is_some());
+        assert_eq!(reply.handle.unwrap().len, 0);
+        assert_eq!(reply.attr, None);
+        assert_eq!(reply.dir_wcc, Nfs3WccData { before: None, after: None });
+    }
+
+    #[test]
+    fn test_parse_nfs3_response_mkdir_fail() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(1u32).to_be_bytes());           // status: NFS3ERR_PERM
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // dir_wcc.before: not present
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // dir_wcc.after: not present
+
+        let (rem, reply) = parse_nfs3_response_mkdir(&buf).unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(reply.status, 1);
+        assert_eq!(reply.handle, None);
+        assert_eq!(reply.attr, None);
+        assert_eq!(reply.dir_wcc, Nfs3WccData { before: None, after: None });
+    }
+
+    fn assert_invalid_readdir_count<O: std::fmt::Debug>(result: IResult<&[u8], O>) {
+        match result {
+            Err(Err::Error(Context::Code(_, ErrorKind::Custom(code)))) => {
+                assert_eq!(code, Nfs3Event::InvalidReaddirCount as u32);
+            }
+            other => panic!("expected Nfs3Event::InvalidReaddirCount, got {:?}", other),
+        }
+    }
+
+    fn empty_handle_bytes() -> Vec<u8> {
+        (0u32).to_be_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_parse_nfs3_request_readdirplus_zero_maxcount() {
+        let mut buf = empty_handle_bytes();
+        buf.extend_from_slice(&(1u32).to_be_bytes());   // cookie
+        buf.extend_from_slice(&[0u8; 8]);                // verifier
+        buf.extend_from_slice(&(100u32).to_be_bytes());  // dircount
+        buf.extend_from_slice(&(0u32).to_be_bytes());    // maxcount: zero, invalid
+
+        assert_invalid_readdir_count(parse_nfs3_request_readdirplus(&buf));
+    }
+
+    #[test]
+    fn test_parse_nfs3_request_readdirplus_maxcount_below_dircount() {
+        let mut buf = empty_handle_bytes();
+        buf.extend_from_slice(&(1u32).to_be_bytes());    // cookie
+        buf.extend_from_slice(&[0u8; 8]);                 // verifier
+        buf.extend_from_slice(&(100u32).to_be_bytes());   // dircount
+        buf.extend_from_slice(&(50u32).to_be_bytes());    // maxcount: below dircount, invalid
+
+        assert_invalid_readdir_count(parse_nfs3_request_readdirplus(&buf));
+    }
+
+    #[test]
+    fn test_parse_nfs3_request_readdir_zero_count() {
+        let mut buf = empty_handle_bytes();
+        buf.extend_from_slice(&(1u64).to_be_bytes());    // cookie
+        buf.extend_from_slice(&[0u8; 8]);                 // verifier
+        buf.extend_from_slice(&(0u32).to_be_bytes());     // count: zero, invalid
+
+        assert_invalid_readdir_count(parse_nfs3_request_readdir(&buf));
+    }
+
+    /// `count` READDIR entries followed by the end-of-list marker, each with
+    /// an empty name so cookie/file_id bytes can't be mistaken for padding.
+    fn readdir_entries_bytes(count: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for i in 0..count {
+            buf.extend_from_slice(&(1u32).to_be_bytes());       // value_follows
+            buf.extend_from_slice(&(i as u64).to_be_bytes());   // file_id
+            buf.extend_from_slice(&(0u32).to_be_bytes());       // name_len
+            buf.extend_from_slice(&(i as u64).to_be_bytes());   // cookie
+        }
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // value_follows: end of list
+        buf
+    }
+
+    #[test]
+    fn test_many0_nfs3_response_readdir_entries_caps_at_max() {
+        let buf = readdir_entries_bytes(NFS3_MAX_READDIR_ENTRIES + 5);
+        let (_rem, entries) = many0_nfs3_response_readdir_entries(&buf).unwrap();
+        assert_eq!(entries.len(), NFS3_MAX_READDIR_ENTRIES);
+    }
+
+    /// `count` READDIRPLUS entries followed by the end-of-list marker, each
+    /// with an empty name and no attrs/handle.
+    fn readdirplus_entries_bytes(count: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for i in 0..count {
+            buf.extend_from_slice(&(1u32).to_be_bytes());       // value_follows
+            buf.extend_from_slice(&(i as u64).to_be_bytes());   // file_id
+            buf.extend_from_slice(&(0u32).to_be_bytes());       // name_len
+            buf.extend_from_slice(&(i as u64).to_be_bytes());   // cookie
+            buf.extend_from_slice(&(0u32).to_be_bytes());       // attr value_follows
+            buf.extend_from_slice(&(0u32).to_be_bytes());       // handle value_follows
+        }
+        buf.extend_from_slice(&(0u32).to_be_bytes());           // value_follows: end of list
+        buf
+    }
+
+    #[test]
+    fn test_many0_nfs3_response_readdirplus_entries_caps_at_max() {
+        let buf = readdirplus_entries_bytes(NFS3_MAX_READDIR_ENTRIES + 5);
+        let (_rem, entries) = many0_nfs3_response_readdirplus_entries(&buf).unwrap();
+        assert_eq!(entries.len(), NFS3_MAX_READDIR_ENTRIES);
+    }
+}